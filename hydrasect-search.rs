@@ -4,7 +4,6 @@
 use std::cmp::min;
 use std::collections::{BTreeMap, BTreeSet};
 use std::env::{self, args};
-use std::ffi::OsStr;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::fs::{create_dir_all, rename, File};
 use std::io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom};
@@ -14,86 +13,20 @@ use std::process::{exit, Command, ExitStatus, Stdio};
 use std::str;
 use std::time::{Duration, SystemTime};
 
-struct OidParseError([u8; 2]);
+use hydrasect::graph::Oid;
+use reqwest::blocking::Client;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH};
 
-impl Display for OidParseError {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let s = String::from_utf8_lossy(&self.0);
-        write!(f, "{:?} cannot be parsed as an octet", s)
-    }
-}
-
-#[test]
-fn test_oid_parse_error_to_string() {
-    let actual = OidParseError([b'g', b'h']).to_string();
-    assert_eq!(actual, r#""gh" cannot be parsed as an octet"#);
-}
-
-impl Debug for OidParseError {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "OidParseError({:?})", String::from_utf8_lossy(&self.0))
-    }
-}
-
-#[test]
-fn test_oid_parse_error_debug() {
-    let actual = format!("{:?}", OidParseError([b'g', b'h']));
-    assert_eq!(actual, r#"OidParseError("gh")"#);
-}
-
-#[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
-struct Oid(Vec<u8>);
-
-impl Oid {
-    fn parse(bytes: &[u8]) -> Result<Self, OidParseError> {
-        let inner = bytes
-            .chunks(2)
-            .map(|pair| {
-                str::from_utf8(pair)
-                    .ok()
-                    .and_then(|s| u8::from_str_radix(s, 16).ok())
-                    .ok_or(OidParseError([pair[0], pair[1]]))
-            })
-            .collect::<Result<_, _>>()?;
-
-        Ok(Self(inner))
-    }
-}
-
-impl Display for Oid {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        for byte in &self.0 {
-            write!(f, "{:02x}", byte)?;
-        }
-        Ok(())
-    }
-}
-
-#[test]
-fn test_oid_display() {
-    let oid = Oid::parse(b"0011f9065a1ad1da4db67bec8d535d91b0a78fba").unwrap();
-    assert_eq!(oid.to_string(), "0011f9065a1ad1da4db67bec8d535d91b0a78fba");
-}
-
-impl Debug for Oid {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "Oid({})", self)
-    }
-}
-
-#[test]
-fn test_oid_debug() {
-    let oid = Oid::parse(b"0011f9065a1ad1da4db67bec8d535d91b0a78fba").unwrap();
-    let debug = format!("{:?}", oid);
-    assert_eq!(debug, "Oid(0011f9065a1ad1da4db67bec8d535d91b0a78fba)");
-}
-
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 struct Commit {
     parents: BTreeSet<Oid>,
     children: BTreeSet<Oid>,
+    /// Committer time (Unix seconds), used to drive the `describe`
+    /// best-first walk.
+    time: i64,
 }
 
+#[cfg(feature = "subprocess-git")]
 fn commit_graph(input: impl BufRead) -> Result<BTreeMap<Oid, Commit>, String> {
     fn parse_oid(s: &[u8]) -> Result<Oid, String> {
         Oid::parse(s).map_err(|e| e.to_string())
@@ -105,14 +38,21 @@ fn commit_graph(input: impl BufRead) -> Result<BTreeMap<Oid, Commit>, String> {
             let line = line.map_err(|e| format!("reading commit graph: {}", e))?;
             let mut fields = line.split(|b| *b == b' ');
             let oid = fields.next().ok_or_else(|| "empty line".to_string())?;
+            let time = fields
+                .next()
+                .ok_or_else(|| "missing commit time".to_string())?;
+            let time = str::from_utf8(time)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| format!("invalid commit time {:?}", String::from_utf8_lossy(time)))?;
             let parents = fields.map(parse_oid).collect::<Result<_, _>>()?;
-            Ok((parse_oid(oid)?, parents))
+            Ok((parse_oid(oid)?, (time, parents)))
         })
-        .collect::<Result<BTreeMap<_, BTreeSet<_>>, String>>()?;
+        .collect::<Result<BTreeMap<_, (i64, BTreeSet<Oid>)>, String>>()?;
 
     // Create a mapping from parent commits to their children.
     let mut paternities = BTreeMap::<_, BTreeSet<_>>::new();
-    for (oid, parents) in &dag {
+    for (oid, (_, parents)) in &dag {
         for parent in parents {
             paternities
                 .entry(parent.clone())
@@ -125,13 +65,14 @@ fn commit_graph(input: impl BufRead) -> Result<BTreeMap<Oid, Commit>, String> {
 
     let undirected_graph = dag
         .into_iter()
-        .map(|(oid, parents)| {
+        .map(|(oid, (time, parents))| {
             let commit = Commit {
                 parents: parents
                     .intersection(&considered_oids)
                     .map(Clone::clone)
                     .collect(),
                 children: paternities.remove(&oid).unwrap_or_default(),
+                time,
             };
             (oid, commit)
         })
@@ -140,10 +81,11 @@ fn commit_graph(input: impl BufRead) -> Result<BTreeMap<Oid, Commit>, String> {
     Ok(undirected_graph)
 }
 
+#[cfg(feature = "subprocess-git")]
 #[test]
 fn test_commit_graph() {
     assert_eq!(
-        commit_graph(&*b"AA BB CC\nCC DD\n".to_vec()).unwrap(),
+        commit_graph(&*b"AA 2 CC\nCC 1 DD\n".to_vec()).unwrap(),
         vec![
             (
                 Oid::parse(b"AA").unwrap(),
@@ -153,6 +95,7 @@ fn test_commit_graph() {
                         .map(|o| Oid::parse(o).unwrap())
                         .collect(),
                     children: BTreeSet::new(),
+                    time: 2,
                 }
             ),
             (
@@ -160,6 +103,7 @@ fn test_commit_graph() {
                 Commit {
                     parents: BTreeSet::new(),
                     children: [Oid::parse(b"AA").unwrap()].into_iter().collect(),
+                    time: 1,
                 }
             ),
         ]
@@ -178,9 +122,10 @@ fn status_to_result(status: ExitStatus, name: &'static str) -> Result<(), String
     Ok(())
 }
 
-fn bisect_graph() -> Result<BTreeMap<Oid, Commit>, String> {
+#[cfg(feature = "subprocess-git")]
+fn bisect_graph(_repo: &gix::Repository) -> Result<BTreeMap<Oid, Commit>, String> {
     let mut child = Command::new("git")
-        .args(&["log", "--format=%H %P", "--bisect"])
+        .args(&["log", "--format=%H %ct %P", "--bisect"])
         .stdout(Stdio::piped())
         .spawn()
         .map_err(|e| format!("failed to spawn git log: {}", e))?;
@@ -195,6 +140,74 @@ fn bisect_graph() -> Result<BTreeMap<Oid, Commit>, String> {
     graph_result.map_err(|e| format!("parsing git log output: {}", e))
 }
 
+/// Same graph `git log --format=%H %ct %P --bisect` would build, computed
+/// in-process with `gix`'s revision walk instead of spawning `git` and
+/// reparsing its stdout.
+#[cfg(not(feature = "subprocess-git"))]
+fn bisect_graph(repo: &gix::Repository) -> Result<BTreeMap<Oid, Commit>, String> {
+    fn to_oid(id: gix::hash::ObjectId) -> Oid {
+        Oid::from_bytes(id.as_bytes().to_vec())
+    }
+
+    let bad = repo
+        .find_reference("refs/bisect/bad")
+        .map_err(|e| format!("resolving refs/bisect/bad: {}", e))?
+        .into_fully_peeled_id()
+        .map_err(|e| format!("peeling refs/bisect/bad: {}", e))?
+        .detach();
+
+    let good: Vec<_> = repo
+        .references()
+        .map_err(|e| format!("listing references: {}", e))?
+        .prefixed("refs/bisect/good-")
+        .map_err(|e| format!("listing refs/bisect/good-*: {}", e))?
+        .filter_map(Result::ok)
+        .map(|mut r| r.peel_to_id_in_place().map(|id| id.detach()))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("peeling refs/bisect/good-*: {}", e))?;
+
+    let mut dag = BTreeMap::<Oid, (i64, BTreeSet<Oid>)>::new();
+    for info in repo
+        .rev_walk([bad])
+        .with_hidden(good)
+        .all()
+        .map_err(|e| format!("walking bisect revisions: {}", e))?
+    {
+        let info = info.map_err(|e| format!("walking bisect revisions: {}", e))?;
+        let commit = info
+            .object()
+            .map_err(|e| format!("reading commit {}: {}", info.id, e))?;
+        let time = commit
+            .committer()
+            .map_err(|e| format!("reading commit {} committer: {}", info.id, e))?
+            .time
+            .seconds;
+        let parents = commit.parent_ids().map(|id| to_oid(id.detach())).collect();
+        dag.insert(to_oid(info.id), (time, parents));
+    }
+
+    let mut paternities = BTreeMap::<Oid, BTreeSet<Oid>>::new();
+    for (oid, (_, parents)) in &dag {
+        for parent in parents {
+            paternities.entry(parent.clone()).or_default().insert(oid.clone());
+        }
+    }
+
+    let considered_oids: BTreeSet<_> = dag.keys().cloned().collect();
+
+    Ok(dag
+        .into_iter()
+        .map(|(oid, (time, parents))| {
+            let commit = Commit {
+                parents: parents.intersection(&considered_oids).cloned().collect(),
+                children: paternities.remove(&oid).unwrap_or_default(),
+                time,
+            };
+            (oid, commit)
+        })
+        .collect())
+}
+
 fn parse_history_line(line: Vec<u8>) -> Oid {
     let oid_str = line
         .into_iter()
@@ -224,54 +237,250 @@ fn test_read_history() {
     assert_eq!(read_history(&*input.to_vec()).unwrap(), expected);
 }
 
-fn closest_commits(
-    start: Oid,
-    graph: BTreeMap<Oid, Commit>,
-    targets: BTreeSet<Oid>,
-) -> BTreeSet<Oid> {
-    let mut candidates: BTreeSet<_> = [start].into_iter().collect();
-    let mut checked = BTreeSet::<Oid>::new();
+/// `bisect_graph`'s `BTreeMap<Oid, Commit>`, interned once per run into
+/// dense integer node ids so `closest_commits` and `describe` can walk
+/// it with `Vec`/`Vec<bool>`-backed frontiers instead of cloning `Oid`s
+/// (and the `BTreeSet`s that own them) at every step — the difference
+/// that matters once `git log --bisect` returns thousands of commits.
+struct InternedGraph {
+    oids: Vec<Oid>,
+    index: BTreeMap<Oid, usize>,
+    parents: Vec<Vec<usize>>,
+    children: Vec<Vec<usize>>,
+    time: Vec<i64>,
+}
+
+impl InternedGraph {
+    fn build(graph: &BTreeMap<Oid, Commit>) -> Self {
+        let oids: Vec<Oid> = graph.keys().cloned().collect();
+        let index: BTreeMap<Oid, usize> = oids
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, oid)| (oid, i))
+            .collect();
+
+        let mut parents = Vec::with_capacity(oids.len());
+        let mut children = Vec::with_capacity(oids.len());
+        let mut time = Vec::with_capacity(oids.len());
+        for oid in &oids {
+            let commit = &graph[oid];
+            parents.push(commit.parents.iter().map(|p| index[p]).collect());
+            children.push(commit.children.iter().map(|c| index[c]).collect());
+            time.push(commit.time);
+        }
+
+        InternedGraph {
+            oids,
+            index,
+            parents,
+            children,
+            time,
+        }
+    }
+
+    fn id(&self, oid: &Oid) -> Option<usize> {
+        self.index.get(oid).copied()
+    }
+
+    /// A `targets[id]` bitset over this graph's node ids, for the Oids
+    /// in `oids` that are actually present in it.
+    fn mask(&self, oids: &BTreeSet<Oid>) -> Vec<bool> {
+        let mut mask = vec![false; self.oids.len()];
+        for oid in oids {
+            if let Some(id) = self.id(oid) {
+                mask[id] = true;
+            }
+        }
+        mask
+    }
+}
+
+fn closest_commits(graph: &InternedGraph, start: usize, targets: &[bool]) -> BTreeSet<Oid> {
+    let mut visited = vec![false; graph.oids.len()];
+    visited[start] = true;
+    let mut frontier = vec![start];
 
     loop {
-        if candidates.is_empty() {
-            return candidates;
+        if frontier.is_empty() {
+            return BTreeSet::new();
         }
 
-        let matches: BTreeSet<_> = candidates
-            .intersection(&targets)
-            .map(Clone::clone)
+        let matches: BTreeSet<Oid> = frontier
+            .iter()
+            .copied()
+            .filter(|&id| targets[id])
+            .map(|id| graph.oids[id].clone())
             .collect();
         if !matches.is_empty() {
             return matches;
         }
 
-        let new_candidates = candidates
+        let mut next = Vec::new();
+        for &id in &frontier {
+            for &neighbor in graph.parents[id].iter().chain(&graph.children[id]) {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    next.push(neighbor);
+                }
+            }
+        }
+        frontier = next;
+    }
+}
+
+/// Level-order breadth-first walk outward from `head` in both
+/// directions — ancestors *and* descendants, matching `closest_commits`'s
+/// search — expanding one full depth at a time so a node's depth is
+/// finalized the instant it's first reached, regardless of committer
+/// time. (An earlier version ordered its queue by time instead of depth,
+/// which could permanently stamp a node with a longer path's depth if a
+/// node on that path merely had a more recent commit time and was popped
+/// first.) Returns the anchor found at the smallest depth — ties
+/// among same-depth anchors broken by the anchor's own, more recent,
+/// commit time — along with that depth, so a Hydra-evaluated commit
+/// either behind or ahead of `head` gets reported.
+fn describe(graph: &InternedGraph, head: usize, anchors: &[bool]) -> Option<(Oid, u32)> {
+    let mut visited = vec![false; graph.oids.len()];
+    visited[head] = true;
+    let mut frontier = vec![head];
+    let mut depth = 0u32;
+
+    loop {
+        if frontier.is_empty() {
+            return None;
+        }
+
+        if let Some(id) = frontier
             .iter()
-            .flat_map(|candidate| {
-                let commit = graph.get(&candidate).unwrap();
-                commit.children.union(&commit.parents)
-            })
-            .map(Clone::clone)
-            .collect::<BTreeSet<_>>()
-            .difference(&checked)
-            .map(Clone::clone)
-            .collect();
-        checked.append(&mut candidates);
-        candidates = new_candidates;
+            .copied()
+            .filter(|&id| anchors[id])
+            .max_by_key(|&id| graph.time[id])
+        {
+            return Some((graph.oids[id].clone(), depth));
+        }
+
+        let mut next = Vec::new();
+        for &id in &frontier {
+            for &neighbor in graph.parents[id].iter().chain(&graph.children[id]) {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    next.push(neighbor);
+                }
+            }
+        }
+        frontier = next;
+        depth += 1;
+    }
+}
+
+/// Build a `commit_graph`-shaped `BTreeMap<Oid, Commit>` directly from
+/// literal `(oid, committer time, parents)` triples, independent of
+/// either backend (`commit_graph`'s text parser is only compiled with
+/// the `subprocess-git` feature), so the `closest_commits`/`describe`
+/// tests run no matter which backend is selected.
+#[cfg(test)]
+fn test_graph(edges: &[(&[u8], i64, &[&[u8]])]) -> BTreeMap<Oid, Commit> {
+    let dag: BTreeMap<Oid, (i64, BTreeSet<Oid>)> = edges
+        .iter()
+        .map(|(oid, time, parents)| {
+            let parents = parents.iter().map(|p| Oid::parse(p).unwrap()).collect();
+            (Oid::parse(oid).unwrap(), (*time, parents))
+        })
+        .collect();
+
+    let mut paternities = BTreeMap::<Oid, BTreeSet<Oid>>::new();
+    for (oid, (_, parents)) in &dag {
+        for parent in parents {
+            paternities.entry(parent.clone()).or_default().insert(oid.clone());
+        }
     }
+
+    let considered_oids: BTreeSet<Oid> = dag.keys().cloned().collect();
+
+    dag.into_iter()
+        .map(|(oid, (time, parents))| {
+            let commit = Commit {
+                parents: parents.intersection(&considered_oids).cloned().collect(),
+                children: paternities.remove(&oid).unwrap_or_default(),
+                time,
+            };
+            (oid, commit)
+        })
+        .collect()
+}
+
+#[test]
+fn test_describe() {
+    let graph = InternedGraph::build(&test_graph(&[
+        (b"AA", 6, &[b"BB".as_slice()]),
+        (b"BB", 5, &[b"CC".as_slice()]),
+        (b"CC", 4, &[b"DD".as_slice(), b"EE".as_slice()]),
+        (b"EE", 2, &[b"FF".as_slice()]),
+        (b"FF", 1, &[b"00".as_slice()]),
+    ]));
+    let anchors = graph.mask(&[b"FF", b"00"].into_iter().map(|o| Oid::parse(o).unwrap()).collect());
+
+    let head = graph.id(&Oid::parse(b"CC").unwrap()).unwrap();
+    let actual = describe(&graph, head, &anchors);
+    assert_eq!(actual, Some((Oid::parse(b"FF").unwrap(), 2)));
+}
+
+/// AA is reachable from CC only by walking *children* (CC is AA's
+/// grandparent, so AA is CC's grandchild): `describe` must search both
+/// directions, like `closest_commits` does, or a Hydra-evaluated commit
+/// ahead of HEAD is never found.
+#[test]
+fn test_describe_descendant() {
+    let graph = InternedGraph::build(&test_graph(&[
+        (b"AA", 6, &[b"BB".as_slice()]),
+        (b"BB", 5, &[b"CC".as_slice()]),
+        (b"CC", 4, &[]),
+    ]));
+    let anchors = graph.mask(&[b"AA"].into_iter().map(|o| Oid::parse(o).unwrap()).collect());
+
+    let head = graph.id(&Oid::parse(b"CC").unwrap()).unwrap();
+    let actual = describe(&graph, head, &anchors);
+    assert_eq!(actual, Some((Oid::parse(b"AA").unwrap(), 2)));
+}
+
+/// A4 is reachable from A0 by two paths of different length: the direct
+/// A0->A1->A4 path (depth 2), and the longer A0->A2->A3->A4 path (depth
+/// 3), where the longer path's intermediate commits have *later*
+/// committer times than the shorter path's. A time-ordered priority
+/// queue would pop A2 (time 100) and A3 (time 50) before A1 (time 5),
+/// discovering A4 via the long path first and permanently stamping it
+/// with depth 3; a correct level-order BFS must still report depth 2.
+#[test]
+fn test_describe_ignores_time_order() {
+    let graph = InternedGraph::build(&test_graph(&[
+        (b"A0", 10, &[b"A1".as_slice(), b"A2".as_slice()]),
+        (b"A1", 5, &[b"A4".as_slice()]),
+        (b"A2", 100, &[b"A3".as_slice()]),
+        (b"A3", 50, &[b"A4".as_slice()]),
+        (b"A4", 1, &[]),
+    ]));
+    let anchors = graph.mask(&[b"A4"].into_iter().map(|o| Oid::parse(o).unwrap()).collect());
+
+    let head = graph.id(&Oid::parse(b"A0").unwrap()).unwrap();
+    let actual = describe(&graph, head, &anchors);
+    assert_eq!(actual, Some((Oid::parse(b"A4").unwrap(), 2)));
 }
 
 #[test]
 fn test_closest_commits() {
-    let graph = b"AA BB\n\
-                  BB CC\n\
-                  CC DD EE\n\
-                  EE FF\n\
-                  FF 00";
     let history = read_history(&*b"AA 0\nFF 0\n00 0\n".to_vec()).unwrap();
-    let graph = commit_graph(&*graph.to_vec()).unwrap();
-
-    let actual = closest_commits(Oid::parse(b"CC").unwrap(), graph, history);
+    let graph = InternedGraph::build(&test_graph(&[
+        (b"AA", 6, &[b"BB".as_slice()]),
+        (b"BB", 5, &[b"CC".as_slice()]),
+        (b"CC", 4, &[b"DD".as_slice(), b"EE".as_slice()]),
+        (b"EE", 2, &[b"FF".as_slice()]),
+        (b"FF", 1, &[b"00".as_slice()]),
+    ]));
+    let targets = graph.mask(&history);
+
+    let start = graph.id(&Oid::parse(b"CC").unwrap()).unwrap();
+    let actual = closest_commits(&graph, start, &targets);
     let expected = [b"AA", b"FF"]
         .into_iter()
         .map(|o| Oid::parse(o).unwrap())
@@ -280,7 +489,8 @@ fn test_closest_commits() {
     assert_eq!(actual, expected);
 }
 
-fn git_rev_parse(commit: impl AsRef<OsStr>) -> Result<Oid, String> {
+#[cfg(feature = "subprocess-git")]
+fn git_rev_parse(_repo: &gix::Repository, commit: &str) -> Result<Oid, String> {
     let out = Command::new("git")
         .arg("rev-parse")
         .arg(commit)
@@ -293,6 +503,16 @@ fn git_rev_parse(commit: impl AsRef<OsStr>) -> Result<Oid, String> {
     Oid::parse(&stdout).map_err(|e| format!("parsing git rev-parse output: {}", e))
 }
 
+/// Resolve a revspec via `gix`'s rev-parse rather than spawning `git
+/// rev-parse` and reparsing its stdout as hex.
+#[cfg(not(feature = "subprocess-git"))]
+fn git_rev_parse(repo: &gix::Repository, spec: &str) -> Result<Oid, String> {
+    let id = repo
+        .rev_parse_single(spec)
+        .map_err(|e| format!("resolving {}: {}", spec, e))?;
+    Ok(Oid::from_bytes(id.detach().as_bytes().to_vec()))
+}
+
 fn last_line(reader: &mut (impl Read + Seek)) -> io::Result<Vec<u8>> {
     let mut buf = vec![0; 4096];
     // Skip an extra character the first time to avoid considering a
@@ -417,7 +637,8 @@ fn test_last_line_long() {
     assert_eq!(data[(len / 2 + 1)..(len - 1)], line);
 }
 
-fn git_is_ancestor(lhs: &dyn AsRef<OsStr>, rhs: &dyn AsRef<OsStr>) -> Result<bool, String> {
+#[cfg(feature = "subprocess-git")]
+fn git_is_ancestor(_repo: &gix::Repository, lhs: &str, rhs: &str) -> Result<bool, String> {
     let status = Command::new("git")
         .args(&["merge-base", "--is-ancestor"])
         .arg(lhs)
@@ -432,59 +653,209 @@ fn git_is_ancestor(lhs: &dyn AsRef<OsStr>, rhs: &dyn AsRef<OsStr>) -> Result<boo
     Ok(true)
 }
 
-fn update_history_file(path: &Path) -> Result<File, String> {
+/// Ancestry check via `gix`'s `merge_base`/`is_ancestor` APIs instead
+/// of spawning `git merge-base --is-ancestor`.
+#[cfg(not(feature = "subprocess-git"))]
+fn git_is_ancestor(repo: &gix::Repository, lhs: &str, rhs: &str) -> Result<bool, String> {
+    let lhs = repo
+        .rev_parse_single(lhs)
+        .map_err(|e| format!("resolving {}: {}", lhs, e))?;
+    let rhs = repo
+        .rev_parse_single(rhs)
+        .map_err(|e| format!("resolving {}: {}", rhs, e))?;
+
+    repo.is_ancestor(lhs, rhs)
+        .map_err(|e| format!("checking ancestry of {} and {}: {}", lhs, rhs, e))
+}
+
+/// What went wrong fetching a fresh history file, keeping the real HTTP
+/// status code around (rather than flattening it into a string right
+/// away) so callers could, in principle, branch on it.
+enum HistoryFetchError {
+    Http(u16),
+    Transport(String),
+    Io(io::Error),
+}
+
+impl Display for HistoryFetchError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            HistoryFetchError::Http(status) => write!(f, "server returned HTTP {}", status),
+            HistoryFetchError::Transport(e) => write!(f, "{}", e),
+            HistoryFetchError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for HistoryFetchError {
+    fn from(e: io::Error) -> Self {
+        HistoryFetchError::Io(e)
+    }
+}
+
+/// Append `.<suffix>` to `path`'s file name, rather than using
+/// `Path::with_extension`, which *replaces* anything after the last
+/// `.` — truncating paths like `hydra-eval-history-nixos-23.11` down to
+/// `hydra-eval-history-nixos-23`.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn etag_path(path: &Path) -> PathBuf {
+    sibling_path(path, "etag")
+}
+
+#[test]
+fn test_sibling_path_dotted_name() {
+    assert_eq!(
+        sibling_path(Path::new("/a/b/hydra-eval-history-nixos-23.11"), "tmp"),
+        PathBuf::from("/a/b/hydra-eval-history-nixos-23.11.tmp")
+    );
+}
+
+/// Format a `SystemTime` as an RFC 7231 HTTP-date (e.g. "Wed, 21 Oct
+/// 2015 07:28:00 GMT") for `If-Modified-Since`, using Howard Hinnant's
+/// `civil_from_days` (http://howardhinnant.github.io/date_algorithms.html)
+/// rather than pulling in a date/time crate for one header.
+fn http_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+
+    let days = secs.div_euclid(86400);
+    let time_of_day = secs.rem_euclid(86400);
+    let (hour, minute, second) = (time_of_day / 3600, (time_of_day / 60) % 60, time_of_day % 60);
+
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = (z - era * 146097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = if month <= 2 { y + 1 } else { y };
+
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[days.rem_euclid(7) as usize],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        hour,
+        minute,
+        second,
+    )
+}
+
+#[test]
+fn test_http_date_epoch() {
+    assert_eq!(http_date(SystemTime::UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+}
+
+#[test]
+fn test_etag_path() {
+    assert_eq!(etag_path(Path::new("/a/b/history")), PathBuf::from("/a/b/history.etag"));
+}
+
+/// Conditionally re-fetch `history_url` into `path`, replacing the
+/// `curl -z` shell-out with an in-process client that sends both
+/// `If-Modified-Since` (the existing file's mtime) and `If-None-Match`
+/// (the `ETag` persisted from the last fetch, alongside `path`), then
+/// streams a changed body to a `.tmp` file and renames it into place.
+fn update_history_file(path: &Path, history_url: &str) -> Result<File, String> {
+    update_history_file_inner(path, history_url).map_err(|e| format!("{}", e))
+}
+
+fn update_history_file_inner(path: &Path, history_url: &str) -> Result<File, HistoryFetchError> {
     if let Some(parent) = path.parent() {
         let _ = create_dir_all(parent);
     }
 
-    let tmp_path = path.with_extension("tmp");
+    let mut request = Client::new().get(history_url);
+    if let Ok(mtime) = std::fs::metadata(path).and_then(|m| m.modified()) {
+        request = request.header(IF_MODIFIED_SINCE, http_date(mtime));
+    }
+    if let Ok(etag) = std::fs::read_to_string(etag_path(path)) {
+        request = request.header(IF_NONE_MATCH, etag.trim().to_owned());
+    }
+
+    let mut response = request
+        .send()
+        .map_err(|e| HistoryFetchError::Transport(e.to_string()))?;
 
-    let status = Command::new("curl")
-        .arg("-fLsSo")
-        .arg(&tmp_path)
-        .arg("-z")
-        .arg(path)
-        .arg("https://channels.nix.gsc.io/nixpkgs-unstable/history")
-        .status()
-        .map_err(|e| format!("spawning curl: {}", e))?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(File::open(path)?);
+    }
+    if !response.status().is_success() {
+        return Err(HistoryFetchError::Http(response.status().as_u16()));
+    }
+
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+
+    let tmp_path = sibling_path(path, "tmp");
+    {
+        let mut tmp_file = File::create(&tmp_path)?;
+        io::copy(&mut response, &mut tmp_file)?;
+    }
+    rename(&tmp_path, path)?;
 
-    if let Some(code) = status.code() {
-        if code > 4 && code != 48 {
-            eprintln!("Warning: failed to update the Hydra evaluation history file.");
+    match etag {
+        Some(etag) => std::fs::write(etag_path(path), etag)?,
+        None => {
+            let _ = std::fs::remove_file(etag_path(path));
         }
     }
-    status_to_result(status, "curl")?;
 
-    match rename(&tmp_path, path) {
-        // If the source file doesn't exist, we got a 304 Not Modified,
-        // so the existing file is up to date.
-        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
-        r => r.map_err(|e| format!("moving new history file into place: {}", e)),
-    }?;
+    Ok(File::open(path)?)
+}
 
-    File::open(&path).map_err(|e| format!("opening updated history file: {}", e))
+/// Resolve an XDG base directory: `$<env_var>` if set and non-empty,
+/// else `$HOME/<home_fallback>`. Shared by the cache dir (history file)
+/// and config dir (channels file) lookups below, which otherwise
+/// independently re-derive the same env-var fallback.
+fn xdg_dir(env_var: &str, home_fallback: &str) -> Result<PathBuf, String> {
+    if let Some(v) = env::var_os(env_var) {
+        if !v.is_empty() {
+            return Ok(v.into());
+        }
+    }
+    match env::var_os("HOME") {
+        Some(v) if !v.is_empty() => {
+            let mut path = PathBuf::from(v);
+            path.push(home_fallback);
+            Ok(path)
+        }
+        _ => Err(format!("{} and HOME are both unset or empty", env_var)),
+    }
 }
 
-fn open_history_file() -> Result<File, String> {
-    let mut path: PathBuf = match env::var_os("XDG_CACHE_HOME") {
-        Some(v) if !v.is_empty() => v.into(),
-        _ => match env::var_os("HOME") {
-            Some(v) if !v.is_empty() => {
-                let mut path_buf = PathBuf::from(v);
-                path_buf.push(".cache");
-                path_buf
-            }
-            _ => {
-                return Err("XDG_CACHE_HOME and HOME are both unset or empty".to_string());
-            }
-        },
-    };
-    path.push("hydrasect/hydra-eval-history");
+fn open_history_file(
+    repo: &gix::Repository,
+    channel: &ChannelConfig,
+) -> Result<File, String> {
+    let mut path = xdg_dir("XDG_CACHE_HOME", ".cache")?;
+    path.push(format!("hydrasect/hydra-eval-history-{}", channel.name));
 
     let mut file = match File::open(&path) {
         Ok(f) => f,
         Err(e) if e.kind() == ErrorKind::NotFound => {
-            return update_history_file(&path).map_err(|e| format!("updating history file: {}", e))
+            return update_history_file(&path, &channel.history_url)
+                .map_err(|e| format!("updating history file: {}", e))
         }
         Err(e) => {
             return Err(format!("opening history file: {}", e));
@@ -496,7 +867,7 @@ fn open_history_file() -> Result<File, String> {
         .map_err(|e| format!("reading last line of history file: {}", e))?;
     file.rewind().unwrap();
 
-    if !git_is_ancestor(&"refs/bisect/bad", &most_recent_eval.to_string())
+    if !git_is_ancestor(repo, "refs/bisect/bad", &most_recent_eval.to_string())
         .map_err(|e| format!("checking history freshness: {}", e))?
     {
         let mtime = file
@@ -507,25 +878,298 @@ fn open_history_file() -> Result<File, String> {
         if SystemTime::now()
             .duration_since(mtime)
             .map_err(|e| format!("checking time since history file modification: {}", e))?
-            > Duration::from_secs(15 * 60)
+            > channel.refresh_interval
         {
-            file = update_history_file(&path)?;
+            file = update_history_file(&path, &channel.history_url)?;
         }
     }
 
     Ok(file)
 }
 
+fn open_repo() -> Result<gix::Repository, String> {
+    gix::discover(".").map_err(|e| format!("opening repository: {}", e))
+}
+
+const DEFAULT_CHANNEL: &str = "nixpkgs-unstable";
+const DEFAULT_HISTORY_URL: &str = "https://channels.nix.gsc.io/nixpkgs-unstable/history";
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+fn config_file_path() -> Result<PathBuf, String> {
+    let mut path = xdg_dir("XDG_CONFIG_HOME", ".config")?;
+    path.push("hydrasect/config");
+    Ok(path)
+}
+
+/// Parse a single config file, recursively merging any `%include`d
+/// files. Sections are keyed by their bracketed header text verbatim
+/// (e.g. `channel "nixpkgs-unstable"`); the empty string stands in for
+/// items that appear before the first header. A missing file parses as
+/// empty, rather than erroring, since a config file is optional.
+fn parse_config_file(path: &Path) -> Result<BTreeMap<String, BTreeMap<String, String>>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(format!("reading {}: {}", path.display(), e)),
+    };
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    parse_config_str(&contents, base_dir)
+}
+
+/// Parse Mercurial-style config syntax: `[section]` headers, `key =
+/// value` items, indented continuation lines appended to the previous
+/// item's value, `#`/`;` whole-line comments, a `%unset key` directive
+/// that drops an already-set item, and a `%include path` directive that
+/// recursively merges another file (resolved relative to this file's
+/// directory).
+fn parse_config_str(
+    contents: &str,
+    base_dir: &Path,
+) -> Result<BTreeMap<String, BTreeMap<String, String>>, String> {
+    let mut config = BTreeMap::<String, BTreeMap<String, String>>::new();
+    let mut section = String::new();
+    let mut last_key: Option<String> = None;
+
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            last_key = None;
+            continue;
+        }
+
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(key) = &last_key {
+                let value = config
+                    .entry(section.clone())
+                    .or_default()
+                    .entry(key.clone())
+                    .or_default();
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+
+        let line = line.trim();
+        if line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("%include") {
+            let included = base_dir.join(rest.trim());
+            for (section, items) in parse_config_file(&included)? {
+                config.entry(section).or_default().extend(items);
+            }
+            last_key = None;
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("%unset") {
+            if let Some(items) = config.get_mut(&section) {
+                items.remove(rest.trim());
+            }
+            last_key = None;
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            section = header.to_owned();
+            last_key = None;
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("expected \"key = value\", found {:?}", line))?;
+        let key = key.trim().to_owned();
+        config
+            .entry(section.clone())
+            .or_default()
+            .insert(key.clone(), value.trim().to_owned());
+        last_key = Some(key);
+    }
+
+    Ok(config)
+}
+
+#[test]
+fn test_parse_config_str() {
+    let config = parse_config_str(
+        "channel = nixos-23.11\n\
+         # a comment\n\
+         ; also a comment\n\
+         refresh-interval = 30m\n\
+         \n\
+         [channel \"nixos-23.11\"]\n\
+         history = https://example.com/history\n\
+         note = line one\n\
+         \x20 line two\n",
+        Path::new("."),
+    )
+    .unwrap();
+
+    assert_eq!(config[""]["channel"], "nixos-23.11");
+    assert_eq!(config[""]["refresh-interval"], "30m");
+    assert_eq!(
+        config["channel \"nixos-23.11\""]["history"],
+        "https://example.com/history"
+    );
+    assert_eq!(config["channel \"nixos-23.11\""]["note"], "line one\nline two");
+}
+
+#[test]
+fn test_parse_config_str_unset() {
+    let config = parse_config_str("key = value\n%unset key\n", Path::new(".")).unwrap();
+    assert!(!config[""].contains_key("key"));
+}
+
+/// Parse a `refresh-interval`-style duration: a bare number of seconds,
+/// or a number suffixed with `s`, `m`, or `h`.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split_at = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, suffix) = s.split_at(split_at);
+    let n: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid duration {:?}", s))?;
+    let secs = match suffix {
+        "" | "s" => n,
+        "m" => n * 60,
+        "h" => n * 60 * 60,
+        _ => return Err(format!("invalid duration suffix {:?} in {:?}", suffix, s)),
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("900").unwrap(), Duration::from_secs(900));
+    assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+    assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+    assert!(parse_duration("15x").is_err());
+}
+
+/// A channel's resolved history URL and cache refresh policy, after
+/// applying `--channel`, the config file's per-channel overrides, and
+/// the built-in defaults, in that order.
+struct ChannelConfig {
+    name: String,
+    history_url: String,
+    refresh_interval: Duration,
+}
+
+fn resolve_channel(
+    config: &BTreeMap<String, BTreeMap<String, String>>,
+    channel_arg: Option<&str>,
+) -> Result<ChannelConfig, String> {
+    let empty = BTreeMap::new();
+    let global = config.get("").unwrap_or(&empty);
+
+    let name = channel_arg
+        .map(str::to_owned)
+        .or_else(|| global.get("channel").cloned())
+        .unwrap_or_else(|| DEFAULT_CHANNEL.to_owned());
+
+    let section = config.get(&format!("channel \"{}\"", name));
+
+    let history_url = section
+        .and_then(|c| c.get("history"))
+        .or_else(|| global.get("history"))
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_HISTORY_URL.to_owned());
+
+    let refresh_interval = section
+        .and_then(|c| c.get("refresh-interval"))
+        .or_else(|| global.get("refresh-interval"))
+        .map(|s| parse_duration(s))
+        .transpose()?
+        .unwrap_or(DEFAULT_REFRESH_INTERVAL);
+
+    Ok(ChannelConfig {
+        name,
+        history_url,
+        refresh_interval,
+    })
+}
+
+#[test]
+fn test_resolve_channel_defaults() {
+    let channel = resolve_channel(&BTreeMap::new(), None).unwrap();
+    assert_eq!(channel.name, DEFAULT_CHANNEL);
+    assert_eq!(channel.history_url, DEFAULT_HISTORY_URL);
+    assert_eq!(channel.refresh_interval, DEFAULT_REFRESH_INTERVAL);
+}
+
+#[test]
+fn test_resolve_channel_override() {
+    let config = parse_config_str(
+        "[channel \"nixos-23.11\"]\n\
+         history = https://example.com/history\n\
+         refresh-interval = 1h\n",
+        Path::new("."),
+    )
+    .unwrap();
+
+    let channel = resolve_channel(&config, Some("nixos-23.11")).unwrap();
+    assert_eq!(channel.history_url, "https://example.com/history");
+    assert_eq!(channel.refresh_interval, Duration::from_secs(3600));
+}
+
+/// Parse the command line for the one option `hydrasect-search`
+/// accepts: `--channel NAME`, selecting which configured channel's
+/// history to search against.
+fn parse_args() -> Result<Option<String>, String> {
+    let mut channel = None;
+    let mut rest = args().skip(1);
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--channel" => {
+                channel = Some(
+                    rest.next()
+                        .ok_or_else(|| "--channel requires an argument".to_string())?,
+                );
+            }
+            other => return Err(format!("unrecognized argument {:?}", other)),
+        }
+    }
+    Ok(channel)
+}
+
 fn run() -> Result<(), String> {
-    let history_file = open_history_file()
+    let repo = open_repo()?;
+
+    let channel_arg = parse_args()?;
+    let config = parse_config_file(&config_file_path()?)?;
+    let channel = resolve_channel(&config, channel_arg.as_deref())?;
+
+    let history_file = open_history_file(&repo, &channel)
         .map(BufReader::new)
         .map_err(|e| format!("opening history file: {}", e))?;
     let history = read_history(history_file).map_err(|e| format!("reading history file: {}", e))?;
-    let head = git_rev_parse("HEAD").map_err(|e| format!("resolving HEAD: {}", e))?;
-    let graph = bisect_graph().map_err(|e| format!("finding bisect graph: {}", e))?;
-
-    for commit in closest_commits(head, graph, history) {
-        println!("{}", commit);
+    let head = git_rev_parse(&repo, "HEAD").map_err(|e| format!("resolving HEAD: {}", e))?;
+    let graph = bisect_graph(&repo).map_err(|e| format!("finding bisect graph: {}", e))?;
+
+    // Interned once so `closest_commits` and `describe` can both walk
+    // `graph` by integer id instead of separately cloning `Oid`s out of
+    // it at every step.
+    let graph = InternedGraph::build(&graph);
+    let targets = graph.mask(&history);
+    let head = graph
+        .id(&head)
+        .ok_or_else(|| format!("{} not found in bisect graph", head))?;
+
+    let candidates = closest_commits(&graph, head, &targets);
+    match describe(&graph, head, &graph.mask(&candidates)) {
+        // Whitespace-separated, with the oid as the first token, to
+        // match `src/main.rs`'s `hydrasect-search` output: `autobisect`
+        // extracts a candidate oid from each line with
+        // `split_whitespace().next()`, and a `anchor-depth` token with
+        // no whitespace in it would be handed over whole, oid and all.
+        Some((anchor, depth)) => println!("{} (+{})", anchor, depth),
+        None => {
+            for commit in candidates {
+                println!("{}", commit);
+            }
+        }
     }
 
     Ok(())
@@ -0,0 +1,118 @@
+//! Drive `git bisect` automatically by asking Hydra for each candidate
+//! revision's build verdict, only falling back to a local build when
+//! Hydra has no conclusive answer.
+
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use reqwest::blocking::Client;
+
+use hydrasect::gitbackend;
+use hydrasect::graph::Oid;
+use hydrasect::history::{open_history_file, parse_history_entry, read_history_entries, HistoryEntry};
+use hydrasect::hydraeval::{classify_eval, fetch_eval, EvalVerdict};
+
+const DEFAULT_HYDRA_URL: &str = "https://hydra.nixos.org";
+const NOTES_REF: &str = "refs/notes/hydra-evals";
+
+/// Resolve a candidate's Hydra eval id from the flat history file first,
+/// falling back to its git note (written by `hydrascrape`'s
+/// `publish_notes` as `"eval_id jobset"`) so a candidate that
+/// `hydrasect-search` found only via notes -- never having made it into
+/// the flat-file history -- doesn't get silently skipped forever.
+fn resolve_eval_id(history: &[HistoryEntry], oid: &str) -> Result<Option<u64>> {
+    if let Some(entry) = history.iter().find(|e| e.revision == oid) {
+        return Ok(Some(entry.eval_id));
+    }
+
+    let repo = gitbackend::open_repo().map_err(|e| anyhow::anyhow!(e))?;
+    let note_oid = Oid::parse(oid.as_bytes()).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    let body = gitbackend::eval_note(&repo, NOTES_REF, &note_oid).map_err(|e| anyhow::anyhow!(e))?;
+    Ok(body
+        .and_then(|body| parse_history_entry(&format!("{oid} {body}")))
+        .map(|entry| entry.eval_id))
+}
+
+fn run_bisect(action: &str, rev: &str) -> Result<()> {
+    let status = Command::new("git")
+        .args(["bisect", action, rev])
+        .status()
+        .with_context(|| format!("spawning git bisect {action}"))?;
+    if !status.success() {
+        bail!("git bisect {action} {rev} exited {status}");
+    }
+    Ok(())
+}
+
+/// Pull the oid out of one line of `hydrasect-search` output. Every
+/// format `hydrasect-search` prints (a bare oid, or an oid followed by
+/// `(+depth)`) keeps the oid as the first whitespace-separated token, so
+/// this is the contract both binaries have to keep: the oid must never
+/// be followed by more of itself without intervening whitespace.
+fn parse_candidate_line(line: &str) -> String {
+    line.split_whitespace().next().unwrap_or_default().to_owned()
+}
+
+#[test]
+fn test_parse_candidate_line() {
+    assert_eq!(
+        parse_candidate_line("0011f9065a1ad1da4db67bec8d535d91b0a78fba (+7)"),
+        "0011f9065a1ad1da4db67bec8d535d91b0a78fba"
+    );
+    assert_eq!(
+        parse_candidate_line("0011f9065a1ad1da4db67bec8d535d91b0a78fba"),
+        "0011f9065a1ad1da4db67bec8d535d91b0a78fba"
+    );
+}
+
+/// Candidate OIDs from `hydrasect-search`, closest to HEAD first.
+fn search_candidates() -> Result<Vec<String>> {
+    let output = Command::new("hydrasect-search")
+        .stdout(Stdio::piped())
+        .output()
+        .context("spawning hydrasect-search")?;
+    if !output.status.success() {
+        bail!("hydrasect-search exited {}", output.status);
+    }
+
+    BufReader::new(&*output.stdout)
+        .lines()
+        .map(|line| Ok(parse_candidate_line(&line.context("reading hydrasect-search output")?)))
+        .collect()
+}
+
+fn main() -> Result<()> {
+    let hydra_url = std::env::var("HYDRA_URL").unwrap_or_else(|_| DEFAULT_HYDRA_URL.to_owned());
+
+    let history_file = open_history_file().map_err(|e| anyhow::anyhow!(e))?;
+    let history: Vec<HistoryEntry> =
+        read_history_entries(BufReader::new(history_file)).map_err(|e| anyhow::anyhow!(e))?;
+
+    let client = Client::new();
+
+    for oid in search_candidates()? {
+        let Some(eval_id) = resolve_eval_id(&history, &oid)? else {
+            continue;
+        };
+
+        let eval = fetch_eval(&client, &hydra_url, eval_id).map_err(|e| anyhow::anyhow!(e))?;
+
+        match classify_eval(&eval) {
+            EvalVerdict::Good => {
+                eprintln!("{oid}: Hydra eval {eval_id} succeeded, marking good");
+                return run_bisect("good", &oid);
+            }
+            EvalVerdict::Bad => {
+                eprintln!("{oid}: Hydra eval {eval_id} failed, marking bad");
+                return run_bisect("bad", &oid);
+            }
+            EvalVerdict::Unbuildable => {
+                eprintln!("{oid}: no conclusive Hydra verdict, skipping");
+                run_bisect("skip", &oid)?;
+            }
+        }
+    }
+
+    bail!("no candidate revision had a conclusive Hydra verdict; build locally instead")
+}
@@ -0,0 +1,181 @@
+// SPDX-FileCopyrightText: 2022 Alyssa Ross <hi@alyssa.is>
+// SPDX-License-Identifier: EUPL-1.2
+
+//! In-process replacement for the `git` subprocess calls used by
+//! `hydrasect-search`, backed by `gix` so large bisect ranges don't pay
+//! for a process spawn and text reparse per query.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use gix::hash::ObjectId;
+use gix::Repository;
+
+use crate::graph::{Commit, CommitGraph, Oid};
+
+fn oid_from_object_id(id: ObjectId) -> Oid {
+    Oid::from_bytes(id.as_bytes().to_vec())
+}
+
+fn object_id_from_oid(oid: &Oid) -> Result<ObjectId, String> {
+    ObjectId::from_hex(oid.to_string().as_bytes()).map_err(|e| format!("invalid oid {}: {}", oid, e))
+}
+
+pub fn open_repo() -> Result<Repository, String> {
+    gix::discover(".").map_err(|e| format!("opening repository: {}", e))
+}
+
+/// Build the same undirected commit graph as `git log --format=%H %P
+/// --bisect`, but by walking refs/bisect/good..refs/bisect/bad directly
+/// with `gix`'s revision walker instead of parsing subprocess output.
+pub fn bisect_graph(repo: &Repository) -> Result<CommitGraph, String> {
+    let bad_ref = repo
+        .find_reference("refs/bisect/bad")
+        .map_err(|e| format!("resolving refs/bisect/bad: {}", e))?;
+    let bad = oid_from_object_id(
+        bad_ref
+            .into_fully_peeled_id()
+            .map_err(|e| format!("peeling refs/bisect/bad: {}", e))?
+            .detach(),
+    );
+
+    let good_revs: Vec<ObjectId> = repo
+        .references()
+        .map_err(|e| format!("listing references: {}", e))?
+        .prefixed("refs/bisect/good-")
+        .map_err(|e| format!("listing refs/bisect/good-*: {}", e))?
+        .filter_map(Result::ok)
+        .map(|mut r| r.peel_to_id_in_place().map(|id| id.detach()))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("peeling refs/bisect/good-*: {}", e))?;
+
+    let tips = object_id_from_oid(&bad).map(|id| vec![id])?;
+
+    let ancestors = repo
+        .rev_walk(tips)
+        .with_hidden(good_revs)
+        .all()
+        .map_err(|e| format!("walking bisect revisions: {}", e))?;
+
+    let mut dag = BTreeMap::<Oid, BTreeSet<Oid>>::new();
+    for info in ancestors {
+        let info = info.map_err(|e| format!("walking bisect revisions: {}", e))?;
+        let commit = info
+            .object()
+            .map_err(|e| format!("reading commit {}: {}", info.id, e))?;
+        let oid = oid_from_object_id(info.id);
+        let parents = commit.parent_ids().map(|id| oid_from_object_id(id.detach())).collect();
+        dag.insert(oid, parents);
+    }
+
+    let mut paternities = BTreeMap::<Oid, BTreeSet<Oid>>::new();
+    for (oid, parents) in &dag {
+        for parent in parents {
+            paternities.entry(parent.clone()).or_default().insert(oid.clone());
+        }
+    }
+
+    let considered_oids: BTreeSet<_> = dag.keys().cloned().collect();
+
+    let commits = dag
+        .into_iter()
+        .map(|(oid, parents)| {
+            let commit = Commit {
+                parents: parents.intersection(&considered_oids).cloned().collect(),
+                children: paternities.remove(&oid).unwrap_or_default(),
+            };
+            (oid, commit)
+        })
+        .collect();
+
+    Ok(CommitGraph {
+        bad: Some(bad),
+        commits,
+    })
+}
+
+/// Resolve a revspec to an `Oid` via `gix`'s rev-parse instead of
+/// spawning `git rev-parse`.
+pub fn rev_parse(repo: &Repository, spec: &str) -> Result<Oid, String> {
+    let id = repo
+        .rev_parse_single(spec)
+        .map_err(|e| format!("resolving {}: {}", spec, e))?;
+    Ok(oid_from_object_id(id.detach()))
+}
+
+/// Check `refs/bisect/skip-<oid>` directly against the refdb rather
+/// than shelling out to `git rev-parse --verify`.
+pub fn commit_not_skipped(repo: &Repository, oid: &Oid) -> Result<bool, String> {
+    match repo.find_reference(&format!("refs/bisect/skip-{}", oid)) {
+        Ok(_) => Ok(false),
+        Err(gix::reference::find::existing::Error::NotFound) => Ok(true),
+        Err(e) => Err(format!("looking up refs/bisect/skip-{}: {}", oid, e)),
+    }
+}
+
+pub fn notes_ref_exists(repo: &Repository, notes_ref: &str) -> Result<bool, String> {
+    match repo.find_reference(notes_ref) {
+        Ok(_) => Ok(true),
+        Err(gix::reference::find::existing::Error::NotFound) => Ok(false),
+        Err(e) => Err(format!("looking up {}: {}", notes_ref, e)),
+    }
+}
+
+/// Read `oid`'s note body under `notes_ref`, if it has one, mirroring
+/// how `git notes` locates a commit's note blob: first by the commit's
+/// full hex oid as a flat path, then, once the notes tree has grown
+/// large enough to be fanned out, by a two-character directory followed
+/// by the remaining hex digits — recursing the same way for each
+/// further fan-out level a sufficiently large notes tree adds, since
+/// `git notes` re-fans-out a directory that itself grows past its
+/// threshold (the same scheme `git` uses for loose objects). The body is
+/// `hydrascrape`'s `publish_notes` message, `"eval_id jobset"`, which is
+/// the only place a notes-only candidate's eval id is recorded.
+pub fn eval_note(repo: &Repository, notes_ref: &str, oid: &Oid) -> Result<Option<String>, String> {
+    let commit = match repo.find_reference(notes_ref) {
+        Ok(r) => r,
+        Err(gix::reference::find::existing::Error::NotFound) => return Ok(None),
+        Err(e) => return Err(format!("looking up {}: {}", notes_ref, e)),
+    }
+    .into_fully_peeled_id()
+    .map_err(|e| format!("peeling {}: {}", notes_ref, e))?
+    .object()
+    .map_err(|e| format!("reading {} commit: {}", notes_ref, e))?;
+
+    let tree = commit
+        .peel_to_tree()
+        .map_err(|e| format!("reading {} tree: {}", notes_ref, e))?;
+
+    Ok(note_blob(tree, &oid.to_string()).map(|data| String::from_utf8_lossy(&data).trim().to_owned()))
+}
+
+/// Check whether `oid` has a note under `notes_ref`. This reads the note
+/// body through `eval_note` and discards it.
+pub fn has_eval_note(repo: &Repository, notes_ref: &str, oid: &Oid) -> Result<bool, String> {
+    Ok(eval_note(repo, notes_ref, oid)?.is_some())
+}
+
+/// Recurse through successive two-character fan-out directories looking
+/// for `hex` (or whatever suffix of it remains once each fan-out level
+/// is stripped), the way `git notes` does when a single directory's
+/// notes have themselves been fanned out more than once, returning the
+/// note blob's content once found.
+fn note_blob(tree: gix::Tree<'_>, hex: &str) -> Option<Vec<u8>> {
+    if let Some(entry) = tree.find_entry(hex) {
+        if let Ok(object) = entry.object() {
+            return Some(object.data.clone());
+        }
+    }
+
+    if hex.len() > 2 {
+        let (dir, rest) = hex.split_at(2);
+        if let Some(subtree) = tree
+            .find_entry(dir)
+            .and_then(|entry| entry.object().ok())
+            .and_then(|object| object.peel_to_tree().ok())
+        {
+            return note_blob(subtree, rest);
+        }
+    }
+
+    None
+}
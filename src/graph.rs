@@ -0,0 +1,105 @@
+// SPDX-FileCopyrightText: 2022 Alyssa Ross <hi@alyssa.is>
+// SPDX-License-Identifier: EUPL-1.2
+
+//! The commit-oid type shared by `hydrasect-search`'s two drivers
+//! (`src/main.rs`'s notes/flat-history search and the root-level
+//! `hydrasect-search.rs`'s channel/config-driven search), plus the
+//! bisect-graph types used by `src/main.rs` and `gitbackend`. The
+//! root-level driver keeps its own `Commit` type, which also records
+//! committer time. `Oid` is also what lets `autobisect` (in
+//! `src/autobisect.rs`) share a single oid type with whichever binary
+//! it's consuming output from.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::str;
+
+pub struct OidParseError([u8; 2]);
+
+impl Display for OidParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let s = String::from_utf8_lossy(&self.0);
+        write!(f, "{:?} cannot be parsed as an octet", s)
+    }
+}
+
+impl Debug for OidParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "OidParseError({:?})", String::from_utf8_lossy(&self.0))
+    }
+}
+
+#[test]
+fn test_oid_parse_error_to_string() {
+    let actual = OidParseError([b'g', b'h']).to_string();
+    assert_eq!(actual, r#""gh" cannot be parsed as an octet"#);
+}
+
+#[test]
+fn test_oid_parse_error_debug() {
+    let actual = format!("{:?}", OidParseError([b'g', b'h']));
+    assert_eq!(actual, r#"OidParseError("gh")"#);
+}
+
+#[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Oid(Vec<u8>);
+
+impl Oid {
+    pub fn parse(bytes: &[u8]) -> Result<Self, OidParseError> {
+        let inner = bytes
+            .chunks(2)
+            .map(|pair| {
+                str::from_utf8(pair)
+                    .ok()
+                    .and_then(|s| u8::from_str_radix(s, 16).ok())
+                    .ok_or(OidParseError([pair[0], pair[1]]))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self(inner))
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Display for Oid {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_oid_display() {
+    let oid = Oid::parse(b"0011f9065a1ad1da4db67bec8d535d91b0a78fba").unwrap();
+    assert_eq!(oid.to_string(), "0011f9065a1ad1da4db67bec8d535d91b0a78fba");
+}
+
+impl Debug for Oid {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Oid({})", self)
+    }
+}
+
+#[test]
+fn test_oid_debug() {
+    let oid = Oid::parse(b"0011f9065a1ad1da4db67bec8d535d91b0a78fba").unwrap();
+    let debug = format!("{:?}", oid);
+    assert_eq!(debug, "Oid(0011f9065a1ad1da4db67bec8d535d91b0a78fba)");
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Commit {
+    pub parents: BTreeSet<Oid>,
+    pub children: BTreeSet<Oid>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CommitGraph {
+    pub bad: Option<Oid>,
+    pub commits: BTreeMap<Oid, Commit>,
+}
@@ -1,5 +1,10 @@
 use log::error;
-use std::{env, fs::File, io::ErrorKind, path::PathBuf};
+use std::{
+    env,
+    fs::File,
+    io::{BufRead, ErrorKind},
+    path::PathBuf,
+};
 
 pub fn history_file_path() -> Result<PathBuf, String> {
     let mut path: PathBuf = match env::var_os("XDG_CACHE_HOME") {
@@ -39,3 +44,49 @@ pub fn open_history_file() -> Result<File, String> {
 
     Ok(file)
 }
+
+/// One scraped `revision eval_id [jobset]` record, as written by
+/// `hydrascrape`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HistoryEntry {
+    pub revision: String,
+    pub eval_id: u64,
+    pub jobset: Option<String>,
+}
+
+pub fn parse_history_entry(line: &str) -> Option<HistoryEntry> {
+    let mut fields = line.split(' ');
+    let revision = fields.next()?.to_owned();
+    let eval_id = fields.next()?.trim().parse().ok()?;
+    let jobset = fields.next().map(str::to_owned);
+    Some(HistoryEntry {
+        revision,
+        eval_id,
+        jobset,
+    })
+}
+
+pub fn read_history_entries(input: impl BufRead) -> Result<Vec<HistoryEntry>, String> {
+    input
+        .lines()
+        .map(|line| {
+            let line = line.map_err(|e| format!("reading history file: {}", e))?;
+            parse_history_entry(&line).ok_or_else(|| format!("malformed history line: {:?}", line))
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_history_entry() {
+    let entry = parse_history_entry("0011f9065a1ad1da4db67bec8d535d91b0a78fba 1496527122 nixos/unstable-small");
+    assert_eq!(
+        entry,
+        Some(HistoryEntry {
+            revision: "0011f9065a1ad1da4db67bec8d535d91b0a78fba".to_owned(),
+            eval_id: 1496527122,
+            jobset: Some("nixos/unstable-small".to_owned()),
+        })
+    );
+
+    assert_eq!(parse_history_entry(""), None);
+}
@@ -0,0 +1,84 @@
+//! Client for Hydra's per-evaluation build-status JSON, used to
+//! classify a cached nixpkgs revision as good, bad, or unbuildable
+//! without doing a local rebuild.
+
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, ACCEPT, USER_AGENT};
+use serde_json::Value;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum EvalVerdict {
+    /// Every job in the evaluation built successfully.
+    Good,
+    /// At least one job failed to build.
+    Bad,
+    /// No conclusive result yet (still building, aborted, or no jobs).
+    Unbuildable,
+}
+
+pub fn fetch_eval(client: &Client, hydra_url: &str, eval_id: u64) -> Result<Value, String> {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, "application/json".parse().unwrap());
+    headers.insert(USER_AGENT, "hydrasect".parse().unwrap());
+
+    let url = format!("{hydra_url}/eval/{eval_id}");
+    client
+        .get(url)
+        .headers(headers)
+        .send()
+        .and_then(|r| r.json())
+        .map_err(|e| format!("fetching eval {eval_id}: {e}"))
+}
+
+/// Classify an evaluation's aggregate build result from its `/eval/{id}`
+/// job summary.
+pub fn classify_eval(eval: &Value) -> EvalVerdict {
+    let builds = match eval.get("builds").and_then(Value::as_object) {
+        Some(b) if !b.is_empty() => b,
+        _ => return EvalVerdict::Unbuildable,
+    };
+
+    let mut any_failed = false;
+    let mut any_pending = false;
+
+    for build in builds.values() {
+        match build.get("buildstatus").and_then(Value::as_i64) {
+            Some(0) => {}
+            Some(_) => any_failed = true,
+            None => any_pending = true,
+        }
+    }
+
+    if any_failed {
+        EvalVerdict::Bad
+    } else if any_pending {
+        EvalVerdict::Unbuildable
+    } else {
+        EvalVerdict::Good
+    }
+}
+
+#[test]
+fn test_classify_eval_good() {
+    let eval = serde_json::json!({
+        "builds": {"hello": {"buildstatus": 0}, "world": {"buildstatus": 0}}
+    });
+    assert_eq!(classify_eval(&eval), EvalVerdict::Good);
+}
+
+#[test]
+fn test_classify_eval_bad() {
+    let eval = serde_json::json!({
+        "builds": {"hello": {"buildstatus": 0}, "world": {"buildstatus": 1}}
+    });
+    assert_eq!(classify_eval(&eval), EvalVerdict::Bad);
+}
+
+#[test]
+fn test_classify_eval_unbuildable() {
+    let eval = serde_json::json!({"builds": {}});
+    assert_eq!(classify_eval(&eval), EvalVerdict::Unbuildable);
+
+    let eval = serde_json::json!({"builds": {"hello": {}}});
+    assert_eq!(classify_eval(&eval), EvalVerdict::Unbuildable);
+}
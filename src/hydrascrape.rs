@@ -1,6 +1,12 @@
-use std::{fs::create_dir_all, io::Write};
+use std::{
+    collections::HashSet,
+    env,
+    fs::{create_dir_all, read_to_string},
+    io::{BufWriter, Write},
+    path::Path,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use reqwest::{
     blocking::Client,
     header::{HeaderMap, ACCEPT, USER_AGENT},
@@ -10,12 +16,110 @@ use tempfile::NamedTempFile;
 
 use hydrasect::history::history_file_path;
 
-const HYDRA_URL: &str = "https://hydra.nixos.org";
-const PROJECT: &str = "nixos";
-const JOBSET: &str = "unstable-small";
+const DEFAULT_HYDRA_URL: &str = "https://hydra.nixos.org";
+const DEFAULT_JOBSET: &str = "nixos/unstable-small";
+
+/// A `project/jobset` pair identifying one Hydra jobset to scrape.
+#[derive(Clone, Eq, PartialEq, Hash)]
+struct Jobset {
+    project: String,
+    jobset: String,
+}
+
+impl std::str::FromStr for Jobset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (project, jobset) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("{s:?} is not a project/jobset pair"))?;
+        Ok(Jobset {
+            project: project.to_owned(),
+            jobset: jobset.to_owned(),
+        })
+    }
+}
+
+impl std::fmt::Display for Jobset {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}", self.project, self.jobset)
+    }
+}
+
+/// `refs/notes/hydra-evals`, the notes ref `hydrasect-search` reads
+/// candidacy from when it's been fetched.
+const NOTES_REF: &str = "refs/notes/hydra-evals";
+
+struct Config {
+    hydra_url: String,
+    jobsets: Vec<Jobset>,
+    full: bool,
+    notes: bool,
+}
+
+fn parse_args() -> Result<Config> {
+    let mut hydra_url = env::var("HYDRA_URL").unwrap_or_else(|_| DEFAULT_HYDRA_URL.to_owned());
+    let mut jobsets = Vec::new();
+    let mut full = false;
+    let mut notes = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--full" => full = true,
+            "--notes" => notes = true,
+            "--url" => {
+                hydra_url = args
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--url requires an argument"))?;
+            }
+            other if other.starts_with("--") => bail!("unrecognized option {other:?}"),
+            other => jobsets.push(other.parse()?),
+        }
+    }
 
-fn fetch_page(client: &Client, page_suffix: &str) -> Result<Value> {
-    let url = format!("{HYDRA_URL}/jobset/{PROJECT}/{JOBSET}/evals{}", page_suffix);
+    if jobsets.is_empty() {
+        jobsets = env::var("JOBSETS")
+            .unwrap_or_else(|_| DEFAULT_JOBSET.to_owned())
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<_>>()?;
+    }
+
+    Ok(Config {
+        hydra_url,
+        jobsets,
+        full,
+        notes,
+    })
+}
+
+/// Publish `new_lines` (`revision eval_id jobset`) as git notes under
+/// `NOTES_REF`, one note per revision, so the mapping can be
+/// fetched/pushed alongside the repository like any other ref.
+fn publish_notes(new_lines: &[String]) -> Result<()> {
+    for line in new_lines {
+        let Some((revision, rest)) = line.split_once(' ') else {
+            continue;
+        };
+
+        let status = std::process::Command::new("git")
+            .args(["notes", "--ref", NOTES_REF, "add", "-f", "-m", rest, revision])
+            .status()
+            .with_context(|| format!("spawning git notes add for {revision}"))?;
+        if !status.success() {
+            bail!("git notes add for {revision} exited {status}");
+        }
+    }
+
+    Ok(())
+}
+
+fn fetch_page(client: &Client, hydra_url: &str, jobset: &Jobset, page_suffix: &str) -> Result<Value> {
+    let url = format!(
+        "{hydra_url}/jobset/{}/{}/evals{page_suffix}",
+        jobset.project, jobset.jobset
+    );
 
     let mut headers = HeaderMap::new();
     headers.insert(ACCEPT, "application/json".parse().unwrap());
@@ -30,26 +134,56 @@ fn parse_page(page_suffix: &str) -> Option<u32> {
         .and_then(|(_first, second)| second.parse().ok())
 }
 
-fn main() -> Result<()> {
-    eprintln!("Scraping all {PROJECT}/{JOBSET} evaluations from {HYDRA_URL}...");
+/// An already-scraped `(jobset, revision, eval id)` triple, as recorded
+/// on one line of the history file.
+fn parse_known_line(line: &str) -> Option<(&str, &str, u64)> {
+    let mut fields = line.split(' ');
+    let revision = fields.next()?;
+    let eval_id = fields.next()?.trim().parse().ok()?;
+    let jobset = fields.next()?;
+    Some((jobset, revision, eval_id))
+}
 
-    let progress = indicatif::ProgressBar::no_length();
-    let client = Client::new();
+/// Read the existing history file, if any, returning its raw lines
+/// (newest first, as written) and the set of `(jobset, revision, eval
+/// id)` triples it already contains.
+fn read_known_evals(path: &Path) -> Result<(Vec<String>, HashSet<(String, String, u64)>)> {
+    let contents = match read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), HashSet::new())),
+        Err(e) => return Err(e.into()),
+    };
 
-    let mut page_suffix: String = "".to_string();
+    let lines: Vec<String> = contents.lines().map(str::to_owned).collect();
+    let known = lines
+        .iter()
+        .filter_map(|line| parse_known_line(line))
+        .map(|(jobset, revision, eval_id)| (jobset.to_owned(), revision.to_owned(), eval_id))
+        .collect();
 
-    let history_file_path = history_file_path().expect("failed to open history file");
-    let mut history_file_dir = history_file_path.clone();
-    history_file_dir.pop();
+    Ok((lines, known))
+}
 
-    create_dir_all(&history_file_dir)?;
+/// Scrape one jobset, returning its new lines (newest first). Stops
+/// early, unless `full` is set, once a page's evals are all already in
+/// `known`.
+fn scrape_jobset(
+    client: &Client,
+    hydra_url: &str,
+    jobset: &Jobset,
+    known: &HashSet<(String, String, u64)>,
+    full: bool,
+) -> Result<Vec<String>> {
+    eprintln!("Scraping all {jobset} evaluations from {hydra_url}...");
 
-    let mut history_file = NamedTempFile::new()?;
+    let progress = indicatif::ProgressBar::no_length();
+    let mut page_suffix: String = "".to_string();
+    let mut new_lines = Vec::new();
 
     loop {
         progress.set_position(parse_page(&page_suffix).unwrap_or(1).into());
 
-        let page_content = fetch_page(&client, &page_suffix)?;
+        let page_content = fetch_page(client, hydra_url, jobset, &page_suffix)?;
         let current_page = page_content.as_object().expect("expected object");
 
         if progress.length().is_none() {
@@ -63,12 +197,16 @@ fn main() -> Result<()> {
             }
         }
 
-        for eval in current_page
+        let evals = current_page
             .get("evals")
             .expect("expected evals key")
             .as_array()
-            .expect("expected array")
-        {
+            .expect("expected array");
+
+        let mut page_lines = Vec::with_capacity(evals.len());
+        let mut all_known = !evals.is_empty();
+
+        for eval in evals {
             let eval = eval.as_object().expect("expected object");
             let eval_id = eval
                 .get("id")
@@ -90,7 +228,19 @@ fn main() -> Result<()> {
                 .expect("expected string")
                 .to_owned();
 
-            history_file.write_all(format!("{revision} {eval_id}\n").as_bytes())?;
+            if known.contains(&(jobset.to_string(), revision.clone(), eval_id)) {
+                continue;
+            }
+            all_known = false;
+
+            page_lines.push(format!("{revision} {eval_id} {jobset}"));
+        }
+
+        new_lines.extend(page_lines);
+
+        if !full && all_known {
+            eprintln!("Reached already-scraped evals for {jobset}; stopping early.");
+            break;
         }
 
         if let Some(next_page_suffix) = current_page.get("next") {
@@ -103,8 +253,53 @@ fn main() -> Result<()> {
         }
     }
 
+    Ok(new_lines)
+}
+
+fn main() -> Result<()> {
+    let config = parse_args()?;
+    let client = Client::new();
+
+    let history_file_path = history_file_path().expect("failed to open history file");
+    let mut history_file_dir = history_file_path.clone();
+    history_file_dir.pop();
+
+    create_dir_all(&history_file_dir)?;
+
+    let (old_lines, known) = if config.full {
+        (Vec::new(), HashSet::new())
+    } else {
+        read_known_evals(&history_file_path)?
+    };
+
+    let mut new_lines = Vec::new();
+    for jobset in &config.jobsets {
+        new_lines.extend(scrape_jobset(
+            &client,
+            &config.hydra_url,
+            jobset,
+            &known,
+            config.full,
+        )?);
+    }
+
+    if config.notes {
+        eprintln!("Publishing new evals to {NOTES_REF}.");
+        publish_notes(&new_lines)?;
+    }
+
+    eprintln!("Merging new evals with existing history.");
+
+    let mut history_file = NamedTempFile::new()?;
+    {
+        let mut writer = BufWriter::new(&mut history_file);
+        for line in new_lines.iter().chain(old_lines.iter()) {
+            writeln!(writer, "{line}")?;
+        }
+    }
+
     eprintln!("Replacing old history file with new data.");
-    history_file.into_temp_path().persist(history_file_path)?;
+    history_file.persist(history_file_path)?;
 
     Ok(())
 }
@@ -120,4 +315,23 @@ mod tests {
         assert_eq!(parse_page("?page=588"), Some(588));
         assert_eq!(parse_page("?page=xxx"), None);
     }
+
+    #[test]
+    fn can_parse_known_line() {
+        assert_eq!(
+            parse_known_line("0011f9065a1ad1da4db67bec8d535d91b0a78fba 1496527122 nixos/unstable-small"),
+            Some(("nixos/unstable-small", "0011f9065a1ad1da4db67bec8d535d91b0a78fba", 1496527122))
+        );
+        assert_eq!(parse_known_line(""), None);
+    }
+
+    #[test]
+    fn can_parse_jobset() {
+        let j: Jobset = "nixos/unstable-small".parse().unwrap();
+        assert_eq!(j.project, "nixos");
+        assert_eq!(j.jobset, "unstable-small");
+        assert_eq!(j.to_string(), "nixos/unstable-small");
+
+        assert!("nixos".parse::<Jobset>().is_err());
+    }
 }
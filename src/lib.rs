@@ -0,0 +1,7 @@
+// SPDX-FileCopyrightText: 2022 Alyssa Ross <hi@alyssa.is>
+// SPDX-License-Identifier: EUPL-1.2
+
+pub mod gitbackend;
+pub mod graph;
+pub mod history;
+pub mod hydraeval;
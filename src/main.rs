@@ -3,102 +3,14 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 use std::env::args;
-use std::ffi::OsStr;
-use std::fmt::{self, Debug, Display, Formatter};
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead};
 use std::iter::once;
-use std::os::unix::prelude::*;
-use std::process::{exit, Command, ExitStatus, Stdio};
-use std::str;
+use std::process::exit;
 
-use hydrasect::history::open_history_file;
-use log::{debug, info};
-
-struct OidParseError([u8; 2]);
-
-impl Display for OidParseError {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let s = String::from_utf8_lossy(&self.0);
-        write!(f, "{:?} cannot be parsed as an octet", s)
-    }
-}
-
-#[test]
-fn test_oid_parse_error_to_string() {
-    let actual = OidParseError([b'g', b'h']).to_string();
-    assert_eq!(actual, r#""gh" cannot be parsed as an octet"#);
-}
-
-impl Debug for OidParseError {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "OidParseError({:?})", String::from_utf8_lossy(&self.0))
-    }
-}
-
-#[test]
-fn test_oid_parse_error_debug() {
-    let actual = format!("{:?}", OidParseError([b'g', b'h']));
-    assert_eq!(actual, r#"OidParseError("gh")"#);
-}
-
-#[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
-struct Oid(Vec<u8>);
-
-impl Oid {
-    fn parse(bytes: &[u8]) -> Result<Self, OidParseError> {
-        let inner = bytes
-            .chunks(2)
-            .map(|pair| {
-                str::from_utf8(pair)
-                    .ok()
-                    .and_then(|s| u8::from_str_radix(s, 16).ok())
-                    .ok_or(OidParseError([pair[0], pair[1]]))
-            })
-            .collect::<Result<_, _>>()?;
-
-        Ok(Self(inner))
-    }
-}
-
-impl Display for Oid {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        for byte in &self.0 {
-            write!(f, "{:02x}", byte)?;
-        }
-        Ok(())
-    }
-}
-
-#[test]
-fn test_oid_display() {
-    let oid = Oid::parse(b"0011f9065a1ad1da4db67bec8d535d91b0a78fba").unwrap();
-    assert_eq!(oid.to_string(), "0011f9065a1ad1da4db67bec8d535d91b0a78fba");
-}
-
-impl Debug for Oid {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "Oid({})", self)
-    }
-}
-
-#[test]
-fn test_oid_debug() {
-    let oid = Oid::parse(b"0011f9065a1ad1da4db67bec8d535d91b0a78fba").unwrap();
-    let debug = format!("{:?}", oid);
-    assert_eq!(debug, "Oid(0011f9065a1ad1da4db67bec8d535d91b0a78fba)");
-}
-
-#[derive(Debug, Default, Eq, PartialEq)]
-struct Commit {
-    parents: BTreeSet<Oid>,
-    children: BTreeSet<Oid>,
-}
-
-#[derive(Debug, PartialEq)]
-struct CommitGraph {
-    bad: Option<Oid>,
-    commits: BTreeMap<Oid, Commit>,
-}
+use hydrasect::gitbackend;
+use hydrasect::graph::{Commit, CommitGraph, Oid};
+use hydrasect::history::{open_history_file, read_history_entries};
+use log::info;
 
 fn commit_graph(input: impl BufRead) -> Result<CommitGraph, String> {
     fn parse_oid(s: &[u8]) -> Result<Oid, String> {
@@ -184,96 +96,66 @@ fn test_commit_graph() {
     );
 }
 
-fn status_to_result(status: ExitStatus, name: &'static str) -> Result<(), String> {
-    if let Some(signal) = status.signal() {
-        return Err(format!("{} killed by signal {}", name, signal));
-    }
-    if !status.success() {
-        return Err(format!("{} exited {}", name, status.code().unwrap()));
-    }
-    Ok(())
-}
-
-fn bool_status_to_result(status: ExitStatus, name: &'static str) -> Result<bool, String> {
-    if status.code() == Some(1) {
-        return Ok(false);
-    }
-    status_to_result(status, name)?;
-    Ok(true)
-}
-
-fn bisect_graph() -> Result<CommitGraph, String> {
-    let mut child = Command::new("git")
-        .args(["log", "--format=%H %P", "--bisect"])
-        .stdout(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("failed to spawn git log: {}", e))?;
-
-    let graph_result = commit_graph(BufReader::new(child.stdout.take().unwrap()));
-
-    let status = child
-        .wait()
-        .map_err(|e| format!("waiting for git: {}", e))?;
-    status_to_result(status, "git log")?;
-
-    graph_result.map_err(|e| format!("parsing git log output: {}", e))
-}
-
-fn parse_history_line(line: Vec<u8>) -> Oid {
-    let oid_str = line
+/// Read the flat history file `hydrascrape` writes (`"revision eval_id
+/// jobset"`) into a map from revision to the jobset on its last line in
+/// the file (the file is written newest first, so that is the revision's
+/// oldest recorded eval), so `run` can report where a cached eval came from
+/// instead of discarding everything but the revision.
+fn read_history(input: impl BufRead) -> Result<BTreeMap<Oid, Option<String>>, String> {
+    read_history_entries(input)?
         .into_iter()
-        .take_while(u8::is_ascii_hexdigit)
-        .collect::<Vec<_>>();
-    Oid::parse(&oid_str).unwrap()
-}
-
-fn read_history(input: impl BufRead) -> io::Result<BTreeSet<Oid>> {
-    input
-        .split(b'\n')
-        .map(|line| Ok(parse_history_line(line?)))
+        .map(|entry| {
+            let oid = Oid::parse(entry.revision.as_bytes()).map_err(|e| e.to_string())?;
+            Ok((oid, entry.jobset))
+        })
         .collect()
 }
 
 #[test]
 fn test_read_history() {
-    let input = b"0011f9065a1ad1da4db67bec8d535d91b0a78fba 1496527122\n\
+    let input = b"0011f9065a1ad1da4db67bec8d535d91b0a78fba 1496527122 nixos/unstable-small\n\
                   0d4431cfe90b2242723ccb1ccc90714f2f68a609 1497692199\n";
     let expected = [
-        b"0011f9065a1ad1da4db67bec8d535d91b0a78fba",
-        b"0d4431cfe90b2242723ccb1ccc90714f2f68a609",
+        (b"0011f9065a1ad1da4db67bec8d535d91b0a78fba".as_slice(), Some("nixos/unstable-small")),
+        (b"0d4431cfe90b2242723ccb1ccc90714f2f68a609".as_slice(), None),
     ]
     .into_iter()
-    .map(|o| Oid::parse(o).unwrap())
+    .map(|(o, jobset)| (Oid::parse(o).unwrap(), jobset.map(str::to_owned)))
     .collect();
     assert_eq!(read_history(&*input.to_vec()).unwrap(), expected);
 }
 
+/// Breadth-first expansion outward from `start`, returning each
+/// surviving target together with the number of edges it took to reach
+/// it — the `git describe`–style "name + depth" pair.
+///
+/// `has_eval` reports whether a commit is a cached Hydra evaluation,
+/// which lets candidacy be read directly from git notes on each commit
+/// during the walk rather than requiring a precomputed target set.
 fn closest_commits(
     start: Oid,
     graph: CommitGraph,
-    mut targets: BTreeSet<Oid>,
-    filter: impl Fn(&Oid) -> Result<bool, String>,
-) -> Result<BTreeSet<Oid>, String> {
+    has_eval: impl Fn(&Oid) -> Result<bool, String>,
+    not_skipped: impl Fn(&Oid) -> Result<bool, String>,
+) -> Result<BTreeSet<(Oid, u32)>, String> {
     let mut candidates: BTreeSet<_> = once(start).collect();
     let mut checked = BTreeSet::<Oid>::new();
-
-    if let Some(ref bad) = graph.bad {
-        targets.remove(bad);
-    }
+    let mut depth: u32 = 0;
 
     loop {
         if candidates.is_empty() {
-            return Ok(candidates);
+            return Ok(BTreeSet::new());
         }
 
-        let matches: BTreeSet<_> = candidates
-            .intersection(&targets)
-            .map(|oid| filter(oid).map(|r| (oid, r)))
-            .filter(|res| !matches!(res, Ok((_, false))))
-            .collect::<Result<BTreeSet<_>, _>>()?
-            .into_iter()
-            .map(|(oid, _)| oid.clone())
-            .collect();
+        let mut matches = BTreeSet::new();
+        for oid in &candidates {
+            if graph.bad.as_ref() == Some(oid) {
+                continue;
+            }
+            if has_eval(oid)? && not_skipped(oid)? {
+                matches.insert((oid.clone(), depth));
+            }
+        }
         if !matches.is_empty() {
             return Ok(matches);
         }
@@ -291,6 +173,7 @@ fn closest_commits(
             .collect();
         checked.append(&mut candidates);
         candidates = new_candidates;
+        depth += 1;
     }
 }
 
@@ -301,14 +184,16 @@ fn test_closest_commits_skip() {
         bad: None,
         commits: once((oid.clone(), Commit::default())).collect(),
     };
-    let history = once(oid.clone()).collect();
-    fn pred(_: &Oid) -> Result<bool, String> {
+    let history = once(oid.clone()).collect::<BTreeSet<_>>();
+    fn not_skipped(_: &Oid) -> Result<bool, String> {
         Ok(false)
     }
 
-    assert!(closest_commits(oid, graph, history, pred)
-        .unwrap()
-        .is_empty());
+    assert!(
+        closest_commits(oid, graph, |o| Ok(history.contains(o)), not_skipped)
+            .unwrap()
+            .is_empty()
+    );
 }
 
 #[test]
@@ -318,61 +203,74 @@ fn test_closest_commits() {
                   CC DD EE\n\
                   EE FF\n\
                   FF 00";
-    let history = read_history(&*b"AA 0\nFF 0\n00 0\n".to_vec()).unwrap();
+    let history = read_history(&*b"AA 0 nixos\nFF 0 nixos\n00 0 nixos\n".to_vec()).unwrap();
     let graph = commit_graph(&*graph.to_vec()).unwrap();
-    fn pred(_: &Oid) -> Result<bool, String> {
+    fn not_skipped(_: &Oid) -> Result<bool, String> {
         Ok(true)
     }
 
-    let actual = closest_commits(Oid::parse(b"CC").unwrap(), graph, history, pred).unwrap();
-    let expected = [b"FF"]
-        .into_iter()
-        .map(|o| Oid::parse(o).unwrap())
-        .collect();
+    let actual = closest_commits(
+        Oid::parse(b"CC").unwrap(),
+        graph,
+        |o| Ok(history.contains_key(o)),
+        not_skipped,
+    )
+    .unwrap();
+    let expected = [(Oid::parse(b"FF").unwrap(), 2)].into_iter().collect();
 
     assert_eq!(actual, expected);
 }
 
-fn git_rev_parse(commit: impl AsRef<OsStr>) -> Result<Oid, String> {
-    let out = Command::new("git")
-        .arg("rev-parse")
-        .arg(commit)
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(|e| format!("spawning git: {}", e))?;
-    status_to_result(out.status, "git rev-parse")?;
-    let mut stdout = out.stdout;
-    stdout.pop();
-    Oid::parse(&stdout).map_err(|e| format!("parsing git rev-parse output: {}", e))
-}
-
-fn commit_not_skipped(oid: &Oid) -> Result<bool, String> {
-    let status = Command::new("git")
-        .args([
-            "rev-parse",
-            "--verify",
-            "-q",
-            &format!("refs/bisect/skip-{}", oid),
-        ])
-        .stdout(Stdio::null())
-        .status()
-        .map_err(|e| format!("spawning git rev-parse --verify: {}", e))?;
-
-    Ok(!bool_status_to_result(status, "git rev-parse --verify")?)
-}
+const NOTES_REF: &str = "refs/notes/hydra-evals";
 
 fn run() -> Result<(), String> {
-    let history_file = open_history_file()
-        .map(BufReader::new)
-        .map_err(|e| format!("opening history file: {}", e))?;
-    let history = read_history(history_file).map_err(|e| format!("reading history file: {}", e))?;
-    let head = git_rev_parse("HEAD").map_err(|e| format!("resolving HEAD: {}", e))?;
-    let graph = bisect_graph().map_err(|e| format!("finding bisect graph: {}", e))?;
-    let commits = closest_commits(head, graph, history, commit_not_skipped)
+    let repo = gitbackend::open_repo().map_err(|e| format!("opening repository: {}", e))?;
+
+    let head = gitbackend::rev_parse(&repo, "HEAD").map_err(|e| format!("resolving HEAD: {}", e))?;
+    let graph = gitbackend::bisect_graph(&repo).map_err(|e| format!("finding bisect graph: {}", e))?;
+
+    // Prefer notes published under NOTES_REF, attached directly to the
+    // nixpkgs commits they evaluate; fall back to the flat history
+    // file for users who haven't fetched that ref. The notes path only
+    // checks whether a note exists and doesn't read the jobset out of
+    // its body, so no jobset is reported for notes-path candidates.
+    let (commits, jobsets) = if gitbackend::notes_ref_exists(&repo, NOTES_REF)
+        .map_err(|e| format!("checking for {}: {}", NOTES_REF, e))?
+    {
+        let commits = closest_commits(
+            head,
+            graph,
+            |oid| gitbackend::has_eval_note(&repo, NOTES_REF, oid),
+            |oid| gitbackend::commit_not_skipped(&repo, oid),
+        )
         .map_err(|e| format!("finding closest commits: {}", e))?;
+        (commits, BTreeMap::new())
+    } else {
+        let history_file = open_history_file()
+            .map(std::io::BufReader::new)
+            .map_err(|e| format!("opening history file: {}", e))?;
+        let jobsets =
+            read_history(history_file).map_err(|e| format!("reading history file: {}", e))?;
+        let commits = closest_commits(
+            head,
+            graph,
+            |oid| Ok(jobsets.contains_key(oid)),
+            |oid| gitbackend::commit_not_skipped(&repo, oid),
+        )
+        .map_err(|e| format!("finding closest commits: {}", e))?;
+        (commits, jobsets)
+    };
 
-    for commit in commits {
-        println!("{}", commit);
+    let mut commits: Vec<_> = commits.into_iter().collect();
+    commits.sort_by(|(a_oid, a_depth), (b_oid, b_depth)| {
+        a_depth.cmp(b_depth).then_with(|| a_oid.cmp(b_oid))
+    });
+
+    for (oid, depth) in commits {
+        match jobsets.get(&oid).and_then(Option::as_ref) {
+            Some(jobset) => println!("{} (+{}) from {}", oid, depth, jobset),
+            None => println!("{} (+{})", oid, depth),
+        }
     }
 
     Ok(())